@@ -12,3 +12,18 @@ pub mod gzip {
         encoder.finish().context("finishing encoding the stream")
     }
 }
+
+pub mod deflate {
+    use anyhow::Context;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    pub fn compress(content: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(content)
+            .context("writing data to deflate encoder")?;
+        encoder.finish().context("finishing encoding the stream")
+    }
+}
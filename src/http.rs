@@ -1,7 +1,7 @@
 use bytes::BytesMut;
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum Method {
     GET,
     POST,
@@ -16,33 +16,194 @@ impl Method {
         };
         Ok(method)
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::GET => "GET",
+            Method::POST => "POST",
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum Status {
     OK,
     Created,
+    PartialContent,
     BadRequest,
+    Forbidden,
     NotFound,
+    NotModified,
+    RangeNotSatisfiable,
     MethodNotAllowed,
     InternalServerError,
 }
 
+/// A small declarative router: routes are registered as `(method, path
+/// pattern) -> handler id` and dispatch decides between a path match, a
+/// method mismatch on a matching path (`405`, with the `Allow` header this
+/// module computes), and no match at all (`404`) — the distinction a
+/// hand-rolled `if`/`strip_prefix` chain tends to get wrong.
+pub mod router {
+    use super::Method;
+
+    #[derive(PartialEq)]
+    enum Segment {
+        /// A fixed path component that must match exactly.
+        Literal(String),
+        /// A `{name}` segment that captures that single path component.
+        Named(String),
+        /// A trailing `*` that captures the rest of the path, slashes
+        /// included. Only meaningful as the pattern's last segment.
+        Wildcard,
+    }
+
+    struct Route {
+        method: Method,
+        segments: Vec<Segment>,
+        id: &'static str,
+    }
+
+    pub struct Router {
+        routes: Vec<Route>,
+    }
+
+    pub enum Match<'a> {
+        /// No registered route's path pattern matched.
+        NotFound,
+        /// The path matched one or more routes, but none for this method.
+        MethodNotAllowed(Vec<Method>),
+        /// A route matched; `captures` holds its path segments in pattern order.
+        Found {
+            id: &'static str,
+            captures: Vec<&'a str>,
+        },
+    }
+
+    impl Default for Router {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Router {
+        pub fn new() -> Self {
+            Self { routes: Vec::new() }
+        }
+
+        /// Registers a route. `pattern` is a `/`-separated path where a
+        /// `{name}` component captures that path segment and a trailing `*`
+        /// captures everything after it, slashes included.
+        pub fn route(mut self, method: Method, pattern: &str, id: &'static str) -> Self {
+            self.routes.push(Route {
+                method,
+                segments: parse_pattern(pattern),
+                id,
+            });
+            self
+        }
+
+        /// Matches `path` against every registered route. A path that
+        /// matches some route but not for `method` yields
+        /// `Match::MethodNotAllowed` with the methods that are registered
+        /// for it, so the caller can answer `405` with an `Allow` header
+        /// instead of a misleading `404`.
+        pub fn dispatch<'a>(&self, method: Method, path: &'a str) -> Match<'a> {
+            let mut allowed = Vec::new();
+
+            for route in &self.routes {
+                let Some(captures) = match_pattern(&route.segments, path) else {
+                    continue;
+                };
+
+                if route.method == method {
+                    return Match::Found {
+                        id: route.id,
+                        captures,
+                    };
+                }
+
+                if !allowed.contains(&route.method) {
+                    allowed.push(route.method);
+                }
+            }
+
+            if allowed.is_empty() {
+                Match::NotFound
+            } else {
+                Match::MethodNotAllowed(allowed)
+            }
+        }
+    }
+
+    fn parse_pattern(pattern: &str) -> Vec<Segment> {
+        pattern
+            .trim_matches('/')
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| match s {
+                "*" => Segment::Wildcard,
+                _ => match s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                    Some(name) => Segment::Named(name.to_string()),
+                    None => Segment::Literal(s.to_string()),
+                },
+            })
+            .collect()
+    }
+
+    fn match_pattern<'a>(segments: &[Segment], path: &'a str) -> Option<Vec<&'a str>> {
+        let mut path = path.trim_start_matches('/');
+
+        if segments.is_empty() {
+            return path.is_empty().then(Vec::new);
+        }
+
+        let mut captures = Vec::new();
+
+        for (i, segment) in segments.iter().enumerate() {
+            if let Segment::Wildcard = segment {
+                captures.push(path);
+                return Some(captures);
+            }
+
+            let (current, rest) = path.split_once('/').unwrap_or((path, ""));
+
+            match segment {
+                Segment::Literal(literal) if current == literal => {}
+                Segment::Named(_) => captures.push(current),
+                _ => return None,
+            }
+
+            path = rest;
+
+            if i == segments.len() - 1 && !path.is_empty() {
+                return None;
+            }
+        }
+
+        Some(captures)
+    }
+}
+
 pub mod request {
     use std::collections::HashMap;
-    use std::path::PathBuf;
-    use std::sync::Arc;
+    use std::io::SeekFrom;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, LazyLock};
+    use std::time::{SystemTime, UNIX_EPOCH};
     use tokio::fs;
-    use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader};
     use tokio::net::TcpStream;
 
     use super::response::Response;
+    use super::router::{Match, Router};
     use super::*;
 
     #[derive(Debug)]
     pub enum RequestError {
-        BadRequestError,
-        MethodNotAllowedError,
+        BadRequest,
+        MethodNotAllowed,
+        ConnectionClosed,
     }
 
     impl std::error::Error for RequestError {}
@@ -53,7 +214,6 @@ pub mod request {
         }
     }
 
-    #[allow(dead_code)]
     pub struct Request {
         config: Arc<Config>,
         http_version: String,
@@ -63,18 +223,37 @@ pub mod request {
         content: Option<Vec<u8>>,
     }
 
+    /// The routing table, built once and shared across every request rather
+    /// than reconstructed per call: with keep-alive, `handle` can now run
+    /// many times over a single connection.
+    ///
+    /// `/echo/*` uses a wildcard rather than a `{msg}` named segment so that
+    /// a message containing `/` is still echoed back in full, matching this
+    /// endpoint's behavior from before routing existed.
+    static ROUTER: LazyLock<Router> = LazyLock::new(|| {
+        Router::new()
+            .route(Method::GET, "/", "root")
+            .route(Method::GET, "/echo/*", "echo")
+            .route(Method::GET, "/user-agent", "user_agent")
+            .route(Method::GET, "/files/*", "files_get")
+            .route(Method::POST, "/files/*", "files_post")
+    });
+
     impl Request {
         pub async fn new(
             reader: &mut BufReader<TcpStream>,
             config: Arc<Config>,
         ) -> anyhow::Result<Self> {
             let mut request_line = String::new();
-            reader.read_line(&mut request_line).await?;
+            if reader.read_line(&mut request_line).await? == 0 {
+                // The client closed the connection instead of sending another request.
+                anyhow::bail!(RequestError::ConnectionClosed);
+            }
 
             let parts: Vec<_> = request_line.split(' ').collect();
             if parts.len() != 3 {
-                eprintln!("Err: {:?} {:?}", RequestError::BadRequestError, parts);
-                anyhow::bail!(RequestError::BadRequestError);
+                eprintln!("Err: {:?} {:?}", RequestError::BadRequest, parts);
+                anyhow::bail!(RequestError::BadRequest);
             }
 
             let method = parts[0];
@@ -85,7 +264,7 @@ pub mod request {
                 Ok(method) => method,
                 Err(err) => {
                     eprintln!("Err: {:?}", err);
-                    anyhow::bail!(RequestError::MethodNotAllowedError);
+                    anyhow::bail!(RequestError::MethodNotAllowed);
                 }
             };
 
@@ -121,84 +300,186 @@ pub mod request {
             Ok(r)
         }
 
+        /// Whether the connection should stay open after this request's response
+        /// is sent. HTTP/1.1 defaults to keep-alive, HTTP/1.0 defaults to close;
+        /// an explicit `Connection` header always wins.
+        pub fn keep_alive(&self) -> bool {
+            match self
+                .headers
+                .get("connection")
+                .map(|v| v.to_ascii_lowercase())
+            {
+                Some(v) if v == "close" => false,
+                Some(v) if v == "keep-alive" => true,
+                _ => self.http_version.trim() == "HTTP/1.1",
+            }
+        }
+
         pub async fn handle(&self) -> Response {
             let encoding = self.headers.get("accept-encoding");
 
-            // GET /
-            if self.path == "/" {
-                if self.method != Method::GET {
-                    return Response::new(Status::MethodNotAllowed);
+            match ROUTER.dispatch(self.method, &self.path) {
+                Match::NotFound => {
+                    eprintln!("Err: path {} {:?}", self.path, Status::NotFound);
+                    Response::new(Status::NotFound)
                 }
-
-                return Response::new(Status::OK);
+                Match::MethodNotAllowed(allowed) => Response::method_not_allowed(&allowed),
+                Match::Found { id: "root", .. } => Response::new(Status::OK),
+                Match::Found {
+                    id: "echo",
+                    captures,
+                } => Response::text(captures[0], encoding),
+                Match::Found {
+                    id: "user_agent", ..
+                } => {
+                    let agent = match self.headers.get("user-agent") {
+                        Some(agent) => agent,
+                        None => "User-Agent header is missing",
+                    };
+                    Response::text(agent, encoding)
+                }
+                Match::Found {
+                    id: "files_get",
+                    captures,
+                } => self.handle_files_get(captures[0], encoding).await,
+                Match::Found {
+                    id: "files_post",
+                    captures,
+                } => self.handle_files_post(captures[0]).await,
+                Match::Found { id, .. } => unreachable!("unhandled route id {id:?}"),
             }
+        }
 
-            // GET /echo/*
-            if let Some(echo) = self.path.strip_prefix("/echo/") {
-                if self.method != Method::GET {
-                    return Response::new(Status::MethodNotAllowed);
-                }
+        /// `GET /files/<name>`: serves the file at `<name>` under the
+        /// configured files directory, with directory listing, conditional
+        /// GET, byte-range and compression support.
+        async fn handle_files_get<'a>(
+            &self,
+            filename: &str,
+            encoding: Option<&'a String>,
+        ) -> Response<'a> {
+            let filedir = match &self.config.files_dir {
+                Some(filedir) => filedir,
+                None => return Response::new(Status::NotFound),
+            };
+            let mut filepath = PathBuf::from(filedir);
+            filepath.push(filename);
 
-                return Response::text(echo, encoding);
-            }
+            let metadata = match fs::metadata(&filepath).await {
+                Ok(metadata) => metadata,
+                Err(_) => return Response::new(Status::NotFound),
+            };
 
-            // GET /user-agent/
-            if self.path.strip_prefix("/user-agent").is_some() {
-                if self.method != Method::GET {
-                    return Response::new(Status::MethodNotAllowed);
-                }
+            let (canonical_path, canonical_root) = match (
+                fs::canonicalize(&filepath).await,
+                fs::canonicalize(filedir).await,
+            ) {
+                (Ok(path), Ok(root)) => (path, root),
+                _ => return Response::new(Status::Forbidden),
+            };
 
-                let agent = match self.headers.get("user-agent") {
-                    Some(agent) => agent,
-                    None => "User-Agent header is missing",
-                };
+            if !canonical_path.starts_with(&canonical_root) {
+                return Response::new(Status::Forbidden);
+            }
 
-                return Response::text(agent, encoding);
+            if metadata.is_dir() {
+                let show_parent = canonical_path != canonical_root;
+                return match directory_index(&filepath, show_parent).await {
+                    Ok(html) => Response::html(html, encoding),
+                    Err(_) => Response::new(Status::InternalServerError),
+                };
             }
 
-            // /files/
-            if let Some(filename) = self.path.strip_prefix("/files/") {
-                let mut filepath: PathBuf;
-
-                let response = match self.method {
-                    // GET /files/ => return file
-                    Method::GET => {
-                        if let Some(filedir) = &self.config.files_dir {
-                            filepath = PathBuf::from(filedir);
-                            filepath.push(filename);
-                        } else {
-                            return Response::new(Status::NotFound);
-                        }
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            let (etag, last_modified) = file_validators(metadata.len(), modified);
+
+            if is_not_modified(
+                self.headers.get("if-none-match"),
+                self.headers.get("if-modified-since"),
+                &etag,
+                modified,
+            ) {
+                return Response::not_modified(etag, last_modified);
+            }
 
-                        let response = match fs::read(filepath).await {
-                            Ok(content) => Response::binary(content, encoding),
-                            Err(_) => Response::new(Status::NotFound),
-                        };
-                        response
+            let content_type = mime_guess::from_path(&filepath)
+                .first_raw()
+                .unwrap_or("application/octet-stream");
+
+            let total = metadata.len();
+            let mut response = match parse_byte_range(self.headers.get("range"), total) {
+                Ok(Some((start, end))) => {
+                    let mut file = match fs::File::open(&filepath).await {
+                        Ok(file) => file,
+                        Err(_) => return Response::new(Status::NotFound),
+                    };
+
+                    let mut buf = vec![0u8; (end - start + 1) as usize];
+                    if file.seek(SeekFrom::Start(start)).await.is_err()
+                        || file.read_exact(&mut buf).await.is_err()
+                    {
+                        return Response::new(Status::InternalServerError);
                     }
-                    // POST /files/ => store file
-                    Method::POST => {
-                        if let Some(filedir) = &self.config.files_dir {
-                            filepath = PathBuf::from(filedir);
-                            filepath.push(filename);
-
-                            if let Some(content) = &self.content {
-                                if fs::write(filepath, content).await.is_err() {
-                                    return Response::new(Status::InternalServerError);
-                                }
-                            }
-                            Response::new(Status::Created)
-                        } else {
-                            Response::new(Status::InternalServerError)
-                        }
+
+                    Response::partial_binary(buf, content_type, start, end, total)
+                }
+                // No range was requested: stream the file straight to the
+                // socket instead of buffering it, unless the client asked
+                // for a content-encoding we can only apply to a buffer.
+                Ok(None) if !Response::wants_compression(encoding) => {
+                    match fs::File::open(&filepath).await {
+                        Ok(file) => Response::stream(file, Some(total), content_type),
+                        Err(_) => return Response::new(Status::NotFound),
                     }
-                };
+                }
+                Ok(None) => match fs::read(&filepath).await {
+                    Ok(content) => Response::binary(content, content_type, encoding),
+                    Err(_) => return Response::new(Status::NotFound),
+                },
+                Err(()) => Response::range_not_satisfiable(total),
+            };
+            response.set_validators(etag, last_modified);
+            response
+        }
+
+        /// `POST /files/<name>`: stores the request body at `<name>` under
+        /// the configured files directory.
+        async fn handle_files_post(&self, filename: &str) -> Response {
+            let Some(filedir) = &self.config.files_dir else {
+                return Response::new(Status::InternalServerError);
+            };
+
+            let mut filepath = PathBuf::from(filedir);
+            filepath.push(filename);
 
-                return response;
+            // The file itself doesn't exist yet, so canonicalize its parent
+            // directory instead and require that to stay under the
+            // configured root - the same guard the GET handler uses against
+            // `../` (and an absolute `filename`, which `PathBuf::push` would
+            // otherwise substitute wholesale) escaping it.
+            let Some(parent) = filepath.parent() else {
+                return Response::new(Status::Forbidden);
+            };
+
+            let (canonical_parent, canonical_root) = match (
+                fs::canonicalize(parent).await,
+                fs::canonicalize(filedir).await,
+            ) {
+                (Ok(parent), Ok(root)) => (parent, root),
+                _ => return Response::new(Status::Forbidden),
+            };
+
+            if !canonical_parent.starts_with(&canonical_root) {
+                return Response::new(Status::Forbidden);
+            }
+
+            if let Some(content) = &self.content {
+                if fs::write(&filepath, content).await.is_err() {
+                    return Response::new(Status::InternalServerError);
+                }
             }
 
-            eprintln!("Err: path {} {:?}", self.path, Status::NotFound);
-            Response::new(Status::NotFound)
+            Response::new(Status::Created)
         }
     }
 
@@ -206,37 +487,235 @@ pub mod request {
     pub struct Config {
         pub files_dir: Option<String>,
     }
+
+    /// Parses a `Range: bytes=<start>-<end>` header against a resource of
+    /// `len` bytes, also accepting open ranges (`bytes=500-`) and suffix
+    /// ranges (`bytes=-500`). Only a single range is supported; a
+    /// multi-range header is treated as absent and the full body is served.
+    ///
+    /// Returns `Ok(Some((start, end)))` for a satisfiable range, `Ok(None)`
+    /// if there is no range to apply, and `Err(())` if the range cannot be
+    /// satisfied (the caller should answer with `416 Range Not Satisfiable`).
+    fn parse_byte_range(header: Option<&String>, len: u64) -> Result<Option<(u64, u64)>, ()> {
+        let Some(header) = header else {
+            return Ok(None);
+        };
+
+        let Some(spec) = header.strip_prefix("bytes=") else {
+            return Ok(None);
+        };
+
+        if spec.contains(',') {
+            return Ok(None);
+        }
+
+        let Some((start, end)) = spec.split_once('-') else {
+            return Ok(None);
+        };
+
+        if len == 0 {
+            return Err(());
+        }
+
+        if start.is_empty() {
+            // Suffix range: the last `end` bytes.
+            let suffix_len: u64 = end.parse().map_err(|_| ())?;
+            if suffix_len == 0 {
+                return Err(());
+            }
+            let suffix_len = suffix_len.min(len);
+            return Ok(Some((len - suffix_len, len - 1)));
+        }
+
+        let start: u64 = start.parse().map_err(|_| ())?;
+        let end = if end.is_empty() {
+            len - 1
+        } else {
+            end.parse::<u64>().map_err(|_| ())?.min(len - 1)
+        };
+
+        if start >= len || start > end {
+            return Err(());
+        }
+
+        Ok(Some((start, end)))
+    }
+
+    /// Renders an HTML index of `dir`'s entries as a list of links,
+    /// HTML-escaping each filename and adding a trailing `../` parent link
+    /// unless `show_parent` is false (i.e. `dir` is the configured root).
+    async fn directory_index(dir: &Path, show_parent: bool) -> anyhow::Result<String> {
+        let mut entries = fs::read_dir(dir).await?;
+        let mut names = Vec::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            let mut name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+                name.push('/');
+            }
+            names.push(name);
+        }
+        names.sort();
+
+        let mut items = String::new();
+        for name in names {
+            let name = html_escape(&name);
+            items.push_str(&format!("<li><a href=\"{name}\">{name}</a></li>"));
+        }
+        if show_parent {
+            items.push_str("<li><a href=\"../\">../</a></li>");
+        }
+
+        Ok(format!("<html><body><ul>{items}</ul></body></html>"))
+    }
+
+    fn html_escape(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&#39;"),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// Computes the weak `ETag` and RFC 7231 `Last-Modified` date for a file,
+    /// derived from its size and modification time.
+    fn file_validators(len: u64, modified: SystemTime) -> (String, String) {
+        let secs = modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        (
+            format!("W/\"{}-{}\"", len, secs),
+            httpdate::fmt_http_date(modified),
+        )
+    }
+
+    /// Whether a conditional GET can be short-circuited to `304 Not Modified`.
+    /// `If-None-Match` takes precedence over `If-Modified-Since` when both
+    /// are present.
+    fn is_not_modified(
+        if_none_match: Option<&String>,
+        if_modified_since: Option<&String>,
+        etag: &str,
+        modified: SystemTime,
+    ) -> bool {
+        if let Some(if_none_match) = if_none_match {
+            return if_none_match
+                .split(',')
+                .any(|tag| matches!(tag.trim(), "*") || tag.trim() == etag);
+        }
+
+        let Some(if_modified_since) = if_modified_since else {
+            return false;
+        };
+
+        let Ok(since) = httpdate::parse_http_date(if_modified_since) else {
+            return false;
+        };
+
+        let modified_secs = modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let since_secs = since
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        modified_secs <= since_secs
+    }
 }
 
 pub mod response {
+    use std::collections::HashMap;
+
+    use tokio::fs::File;
+    use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    use crate::compression::{deflate, gzip};
+
     use super::*;
 
+    /// Size of each chunk streamed to the client for a `Response::stream` body.
+    const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
     pub struct Response<'a> {
         status: Status,
         content: Option<Vec<u8>>,
+        stream: Option<(File, Option<u64>)>,
         content_type: &'a str,
         content_length: usize,
         encoding: Encoding,
+        connection: Option<bool>,
+        accept_ranges: bool,
+        content_range: Option<String>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        allow: Option<String>,
         bytes: BytesMut,
     }
 
-    #[derive(PartialEq)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
     enum Encoding {
         None,
         Gzip,
+        Deflate,
     }
 
     impl Encoding {
-        pub fn from(o: Option<&String>) -> Self {
-            if let Some(encoding) = o {
-                if encoding.contains("gzip") {
-                    Self::Gzip
+        /// Codings we know how to produce. The client's q-values decide between
+        /// them; on equal q-values the coding listed later here is picked.
+        const SUPPORTED: &'static [(&'static str, Encoding)] =
+            &[("gzip", Encoding::Gzip), ("deflate", Encoding::Deflate)];
+
+        /// Parses an `Accept-Encoding` header and picks the supported coding with
+        /// the highest q-value, honoring the `*` wildcard and ignoring codings
+        /// with `q=0`. Returns `Encoding::None` if nothing supported was offered.
+        pub fn from(header: Option<&String>) -> Self {
+            let Some(header) = header else {
+                return Self::None;
+            };
+
+            let mut explicit = HashMap::new();
+            let mut wildcard_q = None;
+
+            for token in header.split(',') {
+                let token = token.trim();
+                if token.is_empty() {
+                    continue;
+                }
+
+                let mut parts = token.split(';');
+                let coding = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+                let q = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+
+                if coding == "*" {
+                    wildcard_q = Some(q);
                 } else {
-                    Self::None
+                    explicit.insert(coding, q);
                 }
-            } else {
-                Self::None
             }
+
+            Self::SUPPORTED
+                .iter()
+                .filter_map(|&(name, encoding)| {
+                    let q = explicit.get(name).copied().or(wildcard_q)?;
+                    (q > 0.0).then_some((encoding, q))
+                })
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(encoding, _)| encoding)
+                .unwrap_or(Self::None)
         }
     }
 
@@ -245,13 +724,55 @@ pub mod response {
             Self {
                 status,
                 content: None,
+                stream: None,
                 content_type: "",
                 content_length: 0,
                 encoding: Encoding::None,
+                connection: None,
+                accept_ranges: false,
+                content_range: None,
+                etag: None,
+                last_modified: None,
+                allow: None,
                 bytes: BytesMut::with_capacity(64),
             }
         }
 
+        /// A `405 Method Not Allowed` response carrying an `Allow` header
+        /// listing the methods that are registered for the matched path.
+        pub fn method_not_allowed(allowed: &[Method]) -> Self {
+            let mut r = Self::new(Status::MethodNotAllowed);
+            r.allow = Some(
+                allowed
+                    .iter()
+                    .map(Method::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+            r
+        }
+
+        /// Sets the `Connection` header to advertise whether this response's
+        /// connection will be kept open for further requests.
+        pub fn set_keep_alive(&mut self, keep_alive: bool) {
+            self.connection = Some(keep_alive);
+        }
+
+        /// Attaches the `ETag` / `Last-Modified` validators for a file response.
+        pub fn set_validators(&mut self, etag: String, last_modified: String) {
+            self.etag = Some(etag);
+            self.last_modified = Some(last_modified);
+        }
+
+        /// A `304 Not Modified` response carrying the validators the client
+        /// can keep relying on.
+        pub fn not_modified(etag: String, last_modified: String) -> Self {
+            let mut r = Self::new(Status::NotModified);
+            r.etag = Some(etag);
+            r.last_modified = Some(last_modified);
+            r
+        }
+
         pub fn text(content: &'a str, encoding: Option<&'a String>) -> Self {
             let mut r = Self::new(Status::OK);
             r.content_type = "text/plain";
@@ -261,21 +782,86 @@ pub mod response {
             r
         }
 
-        pub fn binary(content: Vec<u8>, encoding: Option<&'a String>) -> Self {
+        /// A generated HTML page, such as a directory index.
+        pub fn html(content: String, encoding: Option<&'a String>) -> Self {
             let mut r = Self::new(Status::OK);
-            r.content_type = "application/octet-stream";
+            r.content_type = "text/html";
+            r.content_length = content.len();
+            r.content = Some(content.into_bytes());
+            r.encoding = Encoding::from(encoding);
+            r
+        }
+
+        pub fn binary(
+            content: Vec<u8>,
+            content_type: &'a str,
+            encoding: Option<&'a String>,
+        ) -> Self {
+            let mut r = Self::new(Status::OK);
+            r.content_type = content_type;
             r.content_length = content.len();
             r.content = Some(content);
             r.encoding = Encoding::from(encoding);
+            r.accept_ranges = true;
             r
         }
 
-        pub fn as_bytes(&mut self) -> &[u8] {
+        /// A `206 Partial Content` response carrying a single byte range
+        /// `[start..=end]` out of a resource that is `total` bytes long.
+        pub fn partial_binary(
+            content: Vec<u8>,
+            content_type: &'a str,
+            start: u64,
+            end: u64,
+            total: u64,
+        ) -> Self {
+            let mut r = Self::new(Status::PartialContent);
+            r.content_type = content_type;
+            r.content_length = content.len();
+            r.content = Some(content);
+            r.accept_ranges = true;
+            r.content_range = Some(format!("bytes {}-{}/{}", start, end, total));
+            r
+        }
+
+        /// A `416 Range Not Satisfiable` response for a resource that is
+        /// `total` bytes long.
+        pub fn range_not_satisfiable(total: u64) -> Self {
+            let mut r = Self::new(Status::RangeNotSatisfiable);
+            r.accept_ranges = true;
+            r.content_range = Some(format!("bytes */{}", total));
+            r
+        }
+
+        /// A response whose body is streamed straight from `file` in fixed-size
+        /// chunks instead of being buffered in memory, so serving it stays
+        /// memory-flat regardless of file size. `len`, when known, is sent as
+        /// `Content-Length`; otherwise the body is framed with
+        /// `Transfer-Encoding: chunked`.
+        pub fn stream(file: File, len: Option<u64>, content_type: &'a str) -> Self {
+            let mut r = Self::new(Status::OK);
+            r.content_type = content_type;
+            r.accept_ranges = true;
+            r.stream = Some((file, len));
+            r
+        }
+
+        /// Whether negotiating `encoding` picked a content-coding that requires
+        /// compressing the whole body up front, which rules out streaming it.
+        pub fn wants_compression(encoding: Option<&'a String>) -> bool {
+            Encoding::from(encoding) != Encoding::None
+        }
+
+        fn write_common_headers(&mut self) {
             let status_line = match &self.status {
                 Status::OK => Self::STATUS_200_OK,
                 Status::Created => Self::STATUS_201_CREATED,
+                Status::PartialContent => Self::STATUS_206_PARTIAL_CONTENT,
                 Status::BadRequest => Self::STATUS_400_BAD_REQUEST,
+                Status::Forbidden => Self::STATUS_403_FORBIDDEN,
                 Status::NotFound => Self::STATUS_404_NOT_FOUND,
+                Status::NotModified => Self::STATUS_304_NOT_MODIFIED,
+                Status::RangeNotSatisfiable => Self::STATUS_416_RANGE_NOT_SATISFIABLE,
                 Status::MethodNotAllowed => Self::STATUS_405_METHOD_NOT_ALLOWED,
                 Status::InternalServerError => Self::STATUS_500_INTERNAL_SERVER_ERROR,
             };
@@ -283,11 +869,117 @@ pub mod response {
             self.bytes.extend_from_slice(b"HTTP/1.1 ");
             self.bytes.extend_from_slice(status_line.as_bytes());
 
-            if let Some(content) = &self.content {
+            if let Some(keep_alive) = self.connection {
+                self.bytes.extend_from_slice(b"\r\nConnection: ");
+                self.bytes
+                    .extend_from_slice(if keep_alive { b"keep-alive" } else { b"close" });
+            }
+
+            if self.accept_ranges {
+                self.bytes.extend_from_slice(b"\r\nAccept-Ranges: bytes");
+            }
+
+            if let Some(content_range) = &self.content_range {
+                self.bytes.extend_from_slice(b"\r\nContent-Range: ");
+                self.bytes.extend_from_slice(content_range.as_bytes());
+            }
+
+            if let Some(last_modified) = &self.last_modified {
+                self.bytes.extend_from_slice(b"\r\nLast-Modified: ");
+                self.bytes.extend_from_slice(last_modified.as_bytes());
+            }
+
+            if let Some(etag) = &self.etag {
+                self.bytes.extend_from_slice(b"\r\nETag: ");
+                self.bytes.extend_from_slice(etag.as_bytes());
+            }
+
+            if let Some(allow) = &self.allow {
+                self.bytes.extend_from_slice(b"\r\nAllow: ");
+                self.bytes.extend_from_slice(allow.as_bytes());
+            }
+        }
+
+        /// Writes the response to `writer`: buffered responses are serialized
+        /// with [`Response::as_bytes`], while a [`Response::stream`] body is
+        /// written as a headers block followed by the file copied across in
+        /// fixed-size chunks.
+        pub async fn write_to<W: AsyncWrite + Unpin>(
+            &mut self,
+            writer: &mut W,
+        ) -> std::io::Result<()> {
+            let Some((mut file, len)) = self.stream.take() else {
+                return writer.write_all(self.as_bytes()).await;
+            };
+
+            self.write_common_headers();
+            self.bytes.extend_from_slice(b"\r\nContent-Type: ");
+            self.bytes.extend_from_slice(self.content_type.as_bytes());
+
+            match len {
+                Some(len) => {
+                    self.bytes.extend_from_slice(b"\r\nContent-Length: ");
+                    self.bytes.extend_from_slice(len.to_string().as_bytes());
+                }
+                None => {
+                    self.bytes
+                        .extend_from_slice(b"\r\nTransfer-Encoding: chunked");
+                }
+            }
+            self.bytes.extend_from_slice(b"\r\n\r\n");
+            writer.write_all(&self.bytes).await?;
+
+            let chunked = len.is_none();
+            let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+            loop {
+                let n = file.read(&mut buf).await?;
+                if n == 0 {
+                    break;
+                }
+
+                if chunked {
+                    writer.write_all(format!("{:x}\r\n", n).as_bytes()).await?;
+                    writer.write_all(&buf[..n]).await?;
+                    writer.write_all(b"\r\n").await?;
+                } else {
+                    writer.write_all(&buf[..n]).await?;
+                }
+            }
+
+            if chunked {
+                writer.write_all(b"0\r\n\r\n").await?;
+            }
+
+            Ok(())
+        }
+
+        pub fn as_bytes(&mut self) -> &[u8] {
+            self.write_common_headers();
+
+            if let Some(content) = self.content.take() {
+                let (content, encoding_name) = match self.encoding {
+                    Encoding::Gzip => match gzip::compress(&content) {
+                        Ok(compressed) => (compressed, Some("gzip")),
+                        Err(err) => {
+                            eprintln!("Err: failed to gzip-compress response body: {:?}", err);
+                            (content, None)
+                        }
+                    },
+                    Encoding::Deflate => match deflate::compress(&content) {
+                        Ok(compressed) => (compressed, Some("deflate")),
+                        Err(err) => {
+                            eprintln!("Err: failed to deflate-compress response body: {:?}", err);
+                            (content, None)
+                        }
+                    },
+                    Encoding::None => (content, None),
+                };
+                self.content_length = content.len();
+
                 // Headers
-                if self.encoding == Encoding::Gzip {
+                if let Some(encoding_name) = encoding_name {
                     self.bytes.extend_from_slice(b"\r\nContent-Encoding: ");
-                    self.bytes.extend_from_slice(b"gzip");
+                    self.bytes.extend_from_slice(encoding_name.as_bytes());
                 }
                 self.bytes.extend_from_slice(b"\r\nContent-Type: ");
                 self.bytes.extend_from_slice(self.content_type.as_bytes());
@@ -296,10 +988,19 @@ pub mod response {
                     .extend_from_slice(self.content_length.to_string().as_bytes());
                 self.bytes.extend_from_slice(b"\r\n\r\n");
                 // Content
-                self.bytes.extend_from_slice(content);
-            } else {
-                // No content
+                self.bytes.extend_from_slice(&content);
+
+                self.content = Some(content);
+            } else if matches!(self.status, Status::NotModified) {
+                // A 304 must not carry a body or a header implying one.
                 self.bytes.extend_from_slice(b"\r\n\r\n");
+            } else {
+                // No content, but say so explicitly: without a
+                // Content-Length (or Transfer-Encoding) a keep-alive client
+                // has no way to tell this response ended and will hang
+                // waiting for more body.
+                self.bytes
+                    .extend_from_slice(b"\r\nContent-Length: 0\r\n\r\n");
             }
 
             &self.bytes
@@ -307,8 +1008,12 @@ pub mod response {
 
         const STATUS_200_OK: &'static str = "200 OK";
         const STATUS_201_CREATED: &'static str = "201 Created";
+        const STATUS_206_PARTIAL_CONTENT: &'static str = "206 Partial Content";
         const STATUS_400_BAD_REQUEST: &'static str = "400 Bad Request";
+        const STATUS_403_FORBIDDEN: &'static str = "403 Forbidden";
         const STATUS_404_NOT_FOUND: &'static str = "404 Not Found";
+        const STATUS_304_NOT_MODIFIED: &'static str = "304 Not Modified";
+        const STATUS_416_RANGE_NOT_SATISFIABLE: &'static str = "416 Range Not Satisfiable";
         const STATUS_405_METHOD_NOT_ALLOWED: &'static str = "405 Method Not Allowed";
         const STATUS_500_INTERNAL_SERVER_ERROR: &'static str = "500 Internal Server Error";
     }
@@ -7,6 +7,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::io::BufReader;
 use tokio::net::{TcpListener, TcpStream};
 
+mod compression;
 mod http;
 
 #[tokio::main]
@@ -39,29 +40,40 @@ async fn main() -> anyhow::Result<()> {
 async fn handle_connection(stream: TcpStream, cfg: Arc<Config>) -> anyhow::Result<()> {
     let mut stream = BufReader::new(stream);
 
-    let request = match Request::new(&mut stream, cfg).await {
-        Ok(req) => req,
-        Err(err) => match err.downcast_ref() {
-            Some(RequestError::BadRequestError) => {
-                write_response(&mut stream, Response::new(Status::BadRequest)).await?;
-                return Ok(());
-            }
-            Some(RequestError::MethodNotAllowedError) => {
-                write_response(&mut stream, Response::new(Status::MethodNotAllowed)).await?;
-                return Ok(());
-            }
-            None => anyhow::bail!(err),
-        },
-    };
+    loop {
+        let request = match Request::new(&mut stream, Arc::clone(&cfg)).await {
+            Ok(req) => req,
+            Err(err) => match err.downcast_ref() {
+                Some(RequestError::ConnectionClosed) => return Ok(()),
+                Some(RequestError::BadRequest) => {
+                    write_response(&mut stream, Response::new(Status::BadRequest)).await?;
+                    return Ok(());
+                }
+                Some(RequestError::MethodNotAllowed) => {
+                    write_response(&mut stream, Response::new(Status::MethodNotAllowed)).await?;
+                    return Ok(());
+                }
+                None => anyhow::bail!(err),
+            },
+        };
+
+        let keep_alive = request.keep_alive();
+
+        let mut response = request.handle().await;
+        response.set_keep_alive(keep_alive);
 
-    let mut response = request.handle().await;
+        response.write_to(&mut stream).await?;
+        stream.flush().await?;
 
-    Ok(stream.write_all(response.as_bytes()).await?)
+        if !keep_alive {
+            return Ok(());
+        }
+    }
 }
 
 pub async fn write_response(
     stream: &mut BufReader<TcpStream>,
     mut response: Response<'_>,
 ) -> anyhow::Result<()> {
-    Ok(stream.write_all(response.as_bytes()).await?)
+    Ok(response.write_to(stream).await?)
 }